@@ -1,6 +1,6 @@
 use crate::crypto::Hash;
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +25,27 @@ pub struct BlockHeader {
     pub height: u64,
     /// Validator signature
     pub validator_signature: Option<Vec<u8>>,
+    /// Proof-of-work difficulty target (required leading zero bits); 0 when
+    /// this block was sealed by Proof-of-Authority instead
+    pub difficulty: u32,
+    /// Extra entropy mixed into the mined hash, reseeded periodically so the
+    /// nonce search space doesn't exhaust before finding a winning hash
+    pub random: u32,
+    /// Proof-of-work nonce
+    pub nonce: u64,
+}
+
+/// Nonce increments between advancing `random` to its next value
+const RANDOM_RESEED_INTERVAL: u64 = 1_000_000;
+
+impl BlockHeader {
+    /// Serialize everything in the header except the validator signature,
+    /// for use as the message that gets signed (PoA) or hashed (PoW).
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.validator_signature = None;
+        bincode::serialize(&unsigned).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,6 +78,26 @@ pub enum TransactionType {
     DeleteCache,
 }
 
+impl TransactionType {
+    /// Logical entity category this transaction type affects, used to
+    /// namespace the current-state index (`state:{category}:{key}`)
+    pub fn state_category(&self) -> &'static str {
+        match self {
+            TransactionType::StoreToken | TransactionType::UpdateToken | TransactionType::DeleteToken => {
+                "token"
+            }
+            TransactionType::StoreCache | TransactionType::UpdateCache | TransactionType::DeleteCache => {
+                "cache"
+            }
+        }
+    }
+
+    /// Whether this transaction type tombstones its key in the state index
+    pub fn is_delete(&self) -> bool {
+        matches!(self, TransactionType::DeleteToken | TransactionType::DeleteCache)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionData {
     /// Key for the data (might be a user ID, token ID, etc.)
@@ -65,6 +106,50 @@ pub struct TransactionData {
     pub value: Vec<u8>,
     /// Additional metadata
     pub metadata: HashMap<String, String>,
+    /// Absolute locktime: the stored value isn't valid before this time
+    pub not_before: Option<DateTime<Utc>>,
+    /// Absolute locktime: the stored value isn't valid after this time
+    pub not_after: Option<DateTime<Utc>>,
+    /// Relative locktime, in milliseconds: the stored value matures this
+    /// many milliseconds after the timestamp of the block that committed
+    /// it. Stored as milliseconds rather than `chrono::Duration` because
+    /// the latter doesn't implement `Serialize`/`Deserialize`, and every
+    /// `TransactionData` is bincode-serialized (leaf hashing, state index,
+    /// RPC transport).
+    pub relative_locktime_millis: Option<i64>,
+}
+
+impl Transaction {
+    /// Serialize everything except `signature`, for use as the message that
+    /// gets signed by the transaction's creator (mirrors
+    /// [`BlockHeader::canonical_bytes`]).
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.signature = Vec::new();
+        bincode::serialize(&unsigned).unwrap_or_default()
+    }
+
+    /// Whether this transaction's time-locks allow it to be read at `now`,
+    /// given `committed_at`, the timestamp of the block that committed it
+    /// (needed to resolve `relative_locktime_millis`).
+    pub fn is_valid_at(&self, now: DateTime<Utc>, committed_at: DateTime<Utc>) -> bool {
+        if let Some(not_before) = self.data.not_before {
+            if now < not_before {
+                return false;
+            }
+        }
+        if let Some(not_after) = self.data.not_after {
+            if now > not_after {
+                return false;
+            }
+        }
+        if let Some(relative_locktime_millis) = self.data.relative_locktime_millis {
+            if now < committed_at + Duration::milliseconds(relative_locktime_millis) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl Block {
@@ -76,7 +161,7 @@ impl Block {
     ) -> Self {
         let timestamp = Utc::now();
         let merkle_root = Self::compute_merkle_root(&transactions);
-        
+
         Block {
             header: BlockHeader {
                 version: 1,
@@ -85,15 +170,189 @@ impl Block {
                 timestamp,
                 height,
                 validator_signature: None,
+                difficulty: 0,
+                random: 0,
+                nonce: 0,
             },
             transactions,
         }
     }
-    
-    /// Compute merkle root from transactions
+
+    /// Identifying hash of this block's canonical (unsigned) header bytes
+    pub fn hash(&self) -> Hash {
+        blake3::hash(&self.header.canonical_bytes()).into()
+    }
+
+    /// Alias for [`Block::hash`], named for proof-of-work call sites
+    pub fn pow_hash(&self) -> Hash {
+        self.hash()
+    }
+
+    /// Mine this block: increment `nonce` (reseeding `random` every
+    /// [`RANDOM_RESEED_INTERVAL`] attempts) until [`Block::pow_hash`] has at
+    /// least `difficulty` leading zero bits. Returns the winning hash.
+    pub fn mine(&mut self, difficulty: u32) -> Hash {
+        self.header.difficulty = difficulty;
+        loop {
+            let hash = self.pow_hash();
+            if leading_zero_bits(&hash) >= difficulty {
+                return hash;
+            }
+            self.header.nonce = self.header.nonce.wrapping_add(1);
+            if self.header.nonce % RANDOM_RESEED_INTERVAL == 0 {
+                self.header.random = self.header.random.wrapping_add(1);
+            }
+        }
+    }
+
+    /// Compute the Merkle root over this block's transactions
     fn compute_merkle_root(transactions: &[Transaction]) -> Hash {
-        // Simplified implementation - in production, use a proper Merkle tree
-        let serialized = bincode::serialize(transactions).unwrap_or_default();
-        blake3::hash(&serialized).into()
+        if transactions.is_empty() {
+            return blake3::hash(&[]).into();
+        }
+
+        let mut layer: Vec<Hash> = transactions.iter().map(leaf_hash).collect();
+        while layer.len() > 1 {
+            layer = merkle_layer(&layer);
+        }
+        layer[0]
+    }
+
+    /// Build an inclusion proof for the transaction at `tx_index`.
+    ///
+    /// Each proof element is the sibling hash at that level plus a flag
+    /// indicating whether the sibling sits to the right of the running hash.
+    /// Folding the proof into the leaf hash and comparing against
+    /// `header.merkle_root` (see [`verify_merkle_proof`]) proves inclusion
+    /// without needing the rest of the block's transactions.
+    pub fn merkle_proof(&self, tx_index: usize) -> Option<Vec<(Hash, bool)>> {
+        if tx_index >= self.transactions.len() {
+            return None;
+        }
+
+        let mut layer: Vec<Hash> = self.transactions.iter().map(leaf_hash).collect();
+        let mut index = tx_index;
+        let mut proof = Vec::new();
+
+        while layer.len() > 1 {
+            let sibling_is_right = index % 2 == 0;
+            let sibling_index = if sibling_is_right {
+                (index + 1).min(layer.len() - 1)
+            } else {
+                index - 1
+            };
+            proof.push((layer[sibling_index], sibling_is_right));
+            layer = merkle_layer(&layer);
+            index /= 2;
+        }
+
+        Some(proof)
+    }
+}
+
+/// Hash a single transaction into a Merkle leaf
+fn leaf_hash(transaction: &Transaction) -> Hash {
+    let serialized = bincode::serialize(transaction).unwrap_or_default();
+    blake3::hash(&serialized).into()
+}
+
+/// Pair up adjacent nodes and hash them into the next Merkle layer,
+/// duplicating the last node (Bitcoin-style) when the layer is odd-sized.
+fn merkle_layer(nodes: &[Hash]) -> Vec<Hash> {
+    let mut next = Vec::with_capacity(nodes.len().div_ceil(2));
+    let mut i = 0;
+    while i < nodes.len() {
+        let left = nodes[i];
+        let right = *nodes.get(i + 1).unwrap_or(&left);
+        let mut data = Vec::with_capacity(64);
+        data.extend_from_slice(left.as_bytes());
+        data.extend_from_slice(right.as_bytes());
+        next.push(blake3::hash(&data).into());
+        i += 2;
+    }
+    next
+}
+
+/// Re-check that a block's stored hash meets its claimed proof-of-work difficulty
+pub fn validate_pow(block: &Block) -> bool {
+    leading_zero_bits(&block.pow_hash()) >= block.header.difficulty
+}
+
+/// Count leading zero bits across a hash's bytes
+fn leading_zero_bits(hash: &Hash) -> u32 {
+    let mut bits = 0;
+    for byte in hash.as_bytes() {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Verify that `leaf` is included under `root` given its inclusion `proof`.
+pub fn verify_merkle_proof(leaf: Hash, proof: &[(Hash, bool)], root: &Hash) -> bool {
+    let mut running = leaf;
+    for (sibling, sibling_is_right) in proof {
+        let mut data = Vec::with_capacity(64);
+        if *sibling_is_right {
+            data.extend_from_slice(running.as_bytes());
+            data.extend_from_slice(sibling.as_bytes());
+        } else {
+            data.extend_from_slice(sibling.as_bytes());
+            data.extend_from_slice(running.as_bytes());
+        }
+        running = blake3::hash(&data).into();
+    }
+    &running == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn tx_with_locks(
+        not_before: Option<DateTime<Utc>>,
+        not_after: Option<DateTime<Utc>>,
+        relative_locktime_millis: Option<i64>,
+    ) -> Transaction {
+        Transaction {
+            transaction_type: TransactionType::StoreToken,
+            data: TransactionData {
+                key: "k".to_string(),
+                value: Vec::new(),
+                metadata: HashMap::new(),
+                not_before,
+                not_after,
+                relative_locktime_millis,
+            },
+            timestamp: Utc::now(),
+            signature: Vec::new(),
+            public_key: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn absolute_locktime_window() {
+        let committed_at = Utc::now();
+        let not_before = committed_at + Duration::minutes(1);
+        let not_after = committed_at + Duration::minutes(5);
+        let tx = tx_with_locks(Some(not_before), Some(not_after), None);
+
+        assert!(!tx.is_valid_at(committed_at, committed_at));
+        assert!(tx.is_valid_at(committed_at + Duration::minutes(2), committed_at));
+        assert!(!tx.is_valid_at(committed_at + Duration::minutes(6), committed_at));
+    }
+
+    #[test]
+    fn relative_locktime_matures_after_commit() {
+        let committed_at = Utc::now();
+        let tx = tx_with_locks(None, None, Some(Duration::minutes(10).num_milliseconds()));
+
+        assert!(!tx.is_valid_at(committed_at + Duration::minutes(5), committed_at));
+        assert!(tx.is_valid_at(committed_at + Duration::minutes(11), committed_at));
     }
 }
\ No newline at end of file
@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Result};
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::{Keypair, PublicKey, Signature};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey, StaticSecret};
+
+/// Domain-separation context mixed into the BLAKE3 keyed hash used to derive
+/// the ChaCha20-Poly1305 key from the X25519 shared secret
+const KDF_CONTEXT: &[u8] = b"Acrostic.Storage hybrid encryption v1";
+
+/// Length, in bytes, of the ephemeral public key and nonce prefix on an
+/// [`encrypt`] payload
+const ENCRYPT_HEADER_LEN: usize = 32 + 12;
+
+/// A 32-byte hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hash([u8; 32]);
+
+impl Hash {
+    /// Raw bytes backing this hash
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Lowercase hex encoding, for use in storage keys and RPC responses
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Parse a lowercase hex string produced by [`Hash::to_hex`]
+    pub fn from_hex(hex: &str) -> Result<Hash> {
+        if hex.len() != 64 {
+            return Err(anyhow!("hash hex must be 64 characters, got {}", hex.len()));
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|e| anyhow!("invalid hash hex: {}", e))?;
+        }
+        Ok(Hash(bytes))
+    }
+}
+
+impl From<blake3::Hash> for Hash {
+    fn from(hash: blake3::Hash) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(hash.as_bytes());
+        Hash(bytes)
+    }
+}
+
+/// Generate a new keypair for signing
+pub fn generate_keypair() -> Keypair {
+    let mut csprng = OsRng {};
+    Keypair::generate(&mut csprng)
+}
+
+/// Sign data with a private key
+pub fn sign(data: &[u8], keypair: &Keypair) -> Signature {
+    keypair.sign(data)
+}
+
+/// Verify a signature
+pub fn verify(data: &[u8], signature: &Signature, public_key: &PublicKey) -> bool {
+    public_key.verify(data, signature).is_ok()
+}
+
+/// ECIES-style hybrid encryption: generate an ephemeral X25519 keypair,
+/// derive a shared secret with `recipient_public` via Diffie-Hellman, run it
+/// through a BLAKE3-keyed KDF to produce a ChaCha20-Poly1305 key, and seal
+/// `plaintext` under a fresh random nonce. The output is
+/// `ephemeral_pubkey || nonce || ciphertext || tag`, so a fresh ephemeral key
+/// per call means the (key, nonce) pair is never reused even with a random nonce.
+pub fn encrypt(plaintext: &[u8], recipient_public: &X25519PublicKey) -> Vec<u8> {
+    let ephemeral_secret = EphemeralSecret::new(OsRng);
+    let ephemeral_public = X25519PublicKey::from(&ephemeral_secret);
+    let shared_secret = ephemeral_secret.diffie_hellman(recipient_public);
+
+    let derived_key = blake3::keyed_hash(shared_secret.as_bytes(), KDF_CONTEXT);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(derived_key.as_bytes()));
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    // A fresh key per message means encryption cannot fail here.
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .expect("chacha20poly1305 encryption with a valid key and nonce cannot fail");
+
+    let mut output = Vec::with_capacity(ENCRYPT_HEADER_LEN + ciphertext.len());
+    output.extend_from_slice(ephemeral_public.as_bytes());
+    output.extend_from_slice(&nonce_bytes);
+    output.extend_from_slice(&ciphertext);
+    output
+}
+
+/// Reverse of [`encrypt`] using the recipient's X25519 secret key. Returns
+/// `None` if the payload is too short to contain a header or its
+/// authentication tag fails to verify.
+pub fn decrypt(payload: &[u8], recipient_secret: &StaticSecret) -> Option<Vec<u8>> {
+    if payload.len() < ENCRYPT_HEADER_LEN {
+        return None;
+    }
+
+    let (ephemeral_public_bytes, rest) = payload.split_at(32);
+    let (nonce_bytes, ciphertext) = rest.split_at(12);
+
+    let mut public_bytes = [0u8; 32];
+    public_bytes.copy_from_slice(ephemeral_public_bytes);
+    let ephemeral_public = X25519PublicKey::from(public_bytes);
+
+    let shared_secret = recipient_secret.diffie_hellman(&ephemeral_public);
+    let derived_key = blake3::keyed_hash(shared_secret.as_bytes(), KDF_CONTEXT);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(derived_key.as_bytes()));
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    cipher.decrypt(nonce, ciphertext).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_then_decrypt_round_trips() {
+        let secret = StaticSecret::from([7u8; 32]);
+        let public = X25519PublicKey::from(&secret);
+
+        let ciphertext = encrypt(b"hello from the keychain", &public);
+
+        assert_eq!(decrypt(&ciphertext, &secret).as_deref(), Some(&b"hello from the keychain"[..]));
+    }
+
+    #[test]
+    fn decrypt_rejects_tampered_ciphertext() {
+        let secret = StaticSecret::from([7u8; 32]);
+        let public = X25519PublicKey::from(&secret);
+
+        let mut ciphertext = encrypt(b"hello from the keychain", &public);
+        *ciphertext.last_mut().unwrap() ^= 0xFF; // flip a byte in the auth tag
+
+        assert_eq!(decrypt(&ciphertext, &secret), None);
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_recipient() {
+        let secret = StaticSecret::from([7u8; 32]);
+        let public = X25519PublicKey::from(&secret);
+        let wrong_secret = StaticSecret::from([9u8; 32]);
+
+        let ciphertext = encrypt(b"hello from the keychain", &public);
+
+        assert_eq!(decrypt(&ciphertext, &wrong_secret), None);
+    }
+}
@@ -4,32 +4,137 @@ use libc::{c_char, c_void, size_t};
 use std::ffi::{CStr, CString};
 use std::slice;
 use crate::block::{Block, Transaction, TransactionType, TransactionData};
-use crate::storage::BlockchainStorage;
+use crate::consensus::{ConsensusMode, ProofOfAuthority};
+use crate::crypto;
+use crate::network::PeerManager;
+use crate::storage::{BlockchainStorage, LookupResult};
+use ed25519_dalek::{Keypair, PublicKey};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
 // Global storage instance for C API
 lazy_static::lazy_static! {
     static ref BLOCKCHAIN: Arc<Mutex<Option<BlockchainStorage>>> = Arc::new(Mutex::new(None));
+    static ref PEERS: Mutex<PeerManager> = Mutex::new(PeerManager::new());
+    // X25519 secret this device stores values against. Token/cache values are
+    // only ever written encrypted, so this must be set before NBC_storeData
+    // will succeed; Swift is expected to load it from the device Keychain.
+    static ref ENCRYPTION_KEY: Mutex<Option<StaticSecret>> = Mutex::new(None);
+    // Ordered Proof-of-Authority validator set, shared identically across
+    // every device in the user's sync group (e.g. synced via the same
+    // iCloud Keychain item that carries ENCRYPTION_KEY). Required before
+    // NBC_sealBlock or NBC_syncNow can do anything under Authority mode:
+    // without it every device would otherwise seal under (and verify
+    // against) its own single-validator set, which can never agree with
+    // any other device's.
+    static ref VALIDATOR_SET: Mutex<Option<Vec<PublicKey>>> = Mutex::new(None);
 }
 
-/// Initialize the blockchain
+/// Configure the ordered Proof-of-Authority validator set: `pubkeys` must
+/// point to `pubkeys_len` bytes holding one or more concatenated 32-byte
+/// ed25519 public keys (`pubkeys_len % 32 == 0`). Every device in the sync
+/// group must call this with the same set in the same order — it defines
+/// both sealing turns (`validators[height % validators.len()]`) and what
+/// `NBC_syncNow` accepts from peers. Ignored entirely under Proof-of-Work.
 #[no_mangle]
-pub extern "C" fn NBC_initBlockchain(path: *const c_char) -> bool {
+pub extern "C" fn NBC_setValidatorSet(pubkeys: *const u8, pubkeys_len: size_t) -> bool {
+    if pubkeys.is_null() || pubkeys_len == 0 || pubkeys_len % 32 != 0 {
+        return false;
+    }
+
+    let bytes = unsafe { slice::from_raw_parts(pubkeys, pubkeys_len) };
+    let mut validators = Vec::with_capacity(pubkeys_len / 32);
+    for chunk in bytes.chunks_exact(32) {
+        match PublicKey::from_bytes(chunk) {
+            Ok(public_key) => validators.push(public_key),
+            Err(_) => return false,
+        }
+    }
+
+    *VALIDATOR_SET.lock().unwrap() = Some(validators);
+    true
+}
+
+/// Build the configured `ProofOfAuthority` set, if one has been
+/// configured via `NBC_setValidatorSet`.
+fn configured_authority() -> Option<ProofOfAuthority> {
+    VALIDATOR_SET
+        .lock()
+        .unwrap()
+        .clone()
+        .map(ProofOfAuthority::new)
+}
+
+/// Configure the X25519 secret key that `StoreToken`/`StoreCache` values get
+/// encrypted against. `secret_key` must point to exactly 32 bytes.
+#[no_mangle]
+pub extern "C" fn NBC_setEncryptionKey(secret_key: *const u8, secret_key_len: size_t) -> bool {
+    if secret_key.is_null() || secret_key_len != 32 {
+        return false;
+    }
+
+    let bytes = unsafe { slice::from_raw_parts(secret_key, secret_key_len) };
+    let mut key_bytes = [0u8; 32];
+    key_bytes.copy_from_slice(bytes);
+
+    *ENCRYPTION_KEY.lock().unwrap() = Some(StaticSecret::from(key_bytes));
+    true
+}
+
+/// Encrypt `value` against the configured encryption key for any
+/// transaction type that isn't a tombstone, so stored tokens and cache
+/// entries are always confidential at rest. Returns `None` (refusing the
+/// store) if no encryption key has been configured yet.
+fn encrypt_for_storage(tx_type: &TransactionType, value: Vec<u8>) -> Option<Vec<u8>> {
+    if tx_type.is_delete() {
+        return Some(value);
+    }
+
+    let secret = ENCRYPTION_KEY.lock().unwrap();
+    let secret = secret.as_ref()?;
+    let recipient_public = X25519PublicKey::from(secret);
+    Some(crypto::encrypt(&value, &recipient_public))
+}
+
+/// Initialize the blockchain. A `pow_difficulty` of 0 seals blocks under
+/// Proof-of-Authority; any other value switches to Proof-of-Work mining at
+/// that many required leading zero bits. `block_cache_capacity` and
+/// `state_cache_capacity` size the LRU read caches in front of LevelDB.
+#[no_mangle]
+pub extern "C" fn NBC_initBlockchain(
+    path: *const c_char,
+    pow_difficulty: u32,
+    block_cache_capacity: size_t,
+    state_cache_capacity: size_t,
+) -> bool {
     let c_str = unsafe {
         if path.is_null() {
             return false;
         }
         CStr::from_ptr(path)
     };
-    
+
     let path_str = match c_str.to_str() {
         Ok(s) => s,
         Err(_) => return false,
     };
-    
-    match BlockchainStorage::new(path_str) {
+
+    let consensus_mode = if pow_difficulty == 0 {
+        ConsensusMode::Authority
+    } else {
+        ConsensusMode::ProofOfWork {
+            difficulty: pow_difficulty,
+        }
+    };
+
+    match BlockchainStorage::new(
+        path_str,
+        consensus_mode,
+        block_cache_capacity,
+        state_cache_capacity,
+    ) {
         Ok(storage) => {
             let mut blockchain = BLOCKCHAIN.lock().unwrap();
             *blockchain = Some(storage);
@@ -39,31 +144,46 @@ pub extern "C" fn NBC_initBlockchain(path: *const c_char) -> bool {
     }
 }
 
-/// Store data in the blockchain
+/// `NBC_storeData`/`NBC_storeDataSigned` result codes
+pub const NBC_STORE_ERROR: u32 = 0;
+/// The transaction was queued into the mempool
+pub const NBC_STORE_OK: u32 = 1;
+/// Refused: no key has been configured via `NBC_setEncryptionKey` yet.
+/// Non-delete transaction types are always encrypted at rest, so Swift
+/// must call `NBC_setEncryptionKey` once (typically with a key loaded
+/// from the device Keychain) before the first `NBC_storeData`/
+/// `NBC_storeDataSigned` call.
+pub const NBC_STORE_NO_ENCRYPTION_KEY: u32 = 2;
+
+/// Queue data to be stored in the blockchain. Only lands in the mempool;
+/// call `NBC_sealBlock` to commit it before it's visible to
+/// `NBC_retrieveData`/`NBC_getMerkleProof`. Returns an `NBC_STORE_*` code;
+/// see `NBC_STORE_NO_ENCRYPTION_KEY` for the `NBC_setEncryptionKey`
+/// ordering requirement.
 #[no_mangle]
 pub extern "C" fn NBC_storeData(
     key: *const c_char,
     data: *const c_void,
     data_len: size_t,
     transaction_type: u32,
-) -> bool {
+) -> u32 {
     // Check parameters
     if key.is_null() || data.is_null() || data_len == 0 {
-        return false;
+        return NBC_STORE_ERROR;
     }
-    
+
     // Convert key to Rust string
     let key_cstr = unsafe { CStr::from_ptr(key) };
     let key_str = match key_cstr.to_str() {
         Ok(s) => s.to_string(),
-        Err(_) => return false,
+        Err(_) => return NBC_STORE_ERROR,
     };
-    
+
     // Convert data to Rust bytes
     let data_slice = unsafe {
         slice::from_raw_parts(data as *const u8, data_len)
     };
-    
+
     // Determine transaction type
     let tx_type = match transaction_type {
         0 => TransactionType::StoreToken,
@@ -72,56 +192,208 @@ pub extern "C" fn NBC_storeData(
         3 => TransactionType::StoreCache,
         4 => TransactionType::UpdateCache,
         5 => TransactionType::DeleteCache,
-        _ => return false,
+        _ => return NBC_STORE_ERROR,
     };
-    
+
+    // Values are always encrypted at rest; refuse the store if no
+    // encryption key has been configured yet
+    let value = match encrypt_for_storage(&tx_type, data_slice.to_vec()) {
+        Some(value) => value,
+        None => return NBC_STORE_NO_ENCRYPTION_KEY,
+    };
+
     // Create transaction
     let tx_data = TransactionData {
         key: key_str,
-        value: data_slice.to_vec(),
+        value,
         metadata: HashMap::new(),
+        not_before: None,
+        not_after: None,
+        relative_locktime_millis: None,
     };
-    
+
     let transaction = Transaction {
         transaction_type: tx_type,
         data: tx_data,
         timestamp: chrono::Utc::now(),
-        signature: vec![],  // In real implementation, sign with user's key
-        public_key: vec![],  // In real implementation, use user's public key
+        signature: vec![],  // unsigned; use NBC_storeDataSigned to authenticate authorship
+        public_key: vec![],
     };
-    
+
     // Add to blockchain
-    let blockchain = BLOCKCHAIN.lock().unwrap();
-    if let Some(storage) = &*blockchain {
+    let mut blockchain = BLOCKCHAIN.lock().unwrap();
+    if let Some(storage) = &mut *blockchain {
         match storage.add_transaction(transaction) {
-            Ok(_) => true,
-            Err(_) => false,
+            Ok(_) => NBC_STORE_OK,
+            Err(_) => NBC_STORE_ERROR,
         }
     } else {
-        false
+        NBC_STORE_ERROR
+    }
+}
+
+/// Queue data to be stored in the blockchain, signed by the caller's
+/// ed25519 keypair so `ProofOfAuthority::verify_block` (and anyone else
+/// inspecting the chain) can authenticate who authored the transaction.
+/// `keypair` must point to exactly 64 bytes: the ed25519 secret key
+/// followed by its public key. Only lands in the mempool; call
+/// `NBC_sealBlock` to commit it before it's visible to
+/// `NBC_retrieveData`/`NBC_getMerkleProof`. Returns an `NBC_STORE_*` code;
+/// see `NBC_STORE_NO_ENCRYPTION_KEY` for the `NBC_setEncryptionKey`
+/// ordering requirement.
+#[no_mangle]
+pub extern "C" fn NBC_storeDataSigned(
+    key: *const c_char,
+    data: *const c_void,
+    data_len: size_t,
+    transaction_type: u32,
+    keypair: *const u8,
+    keypair_len: size_t,
+) -> u32 {
+    if key.is_null() || data.is_null() || data_len == 0 || keypair.is_null() || keypair_len != 64 {
+        return NBC_STORE_ERROR;
+    }
+
+    let key_cstr = unsafe { CStr::from_ptr(key) };
+    let key_str = match key_cstr.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return NBC_STORE_ERROR,
+    };
+
+    let data_slice = unsafe { slice::from_raw_parts(data as *const u8, data_len) };
+    let keypair_bytes = unsafe { slice::from_raw_parts(keypair, keypair_len) };
+    let keypair = match Keypair::from_bytes(keypair_bytes) {
+        Ok(keypair) => keypair,
+        Err(_) => return NBC_STORE_ERROR,
+    };
+
+    let tx_type = match transaction_type {
+        0 => TransactionType::StoreToken,
+        1 => TransactionType::UpdateToken,
+        2 => TransactionType::DeleteToken,
+        3 => TransactionType::StoreCache,
+        4 => TransactionType::UpdateCache,
+        5 => TransactionType::DeleteCache,
+        _ => return NBC_STORE_ERROR,
+    };
+
+    let value = match encrypt_for_storage(&tx_type, data_slice.to_vec()) {
+        Some(value) => value,
+        None => return NBC_STORE_NO_ENCRYPTION_KEY,
+    };
+
+    let tx_data = TransactionData {
+        key: key_str,
+        value,
+        metadata: HashMap::new(),
+        not_before: None,
+        not_after: None,
+        relative_locktime_millis: None,
+    };
+
+    let mut transaction = Transaction {
+        transaction_type: tx_type,
+        data: tx_data,
+        timestamp: chrono::Utc::now(),
+        signature: vec![],
+        public_key: keypair.public.to_bytes().to_vec(),
+    };
+    transaction.signature = crypto::sign(&transaction.canonical_bytes(), &keypair)
+        .to_bytes()
+        .to_vec();
+
+    let mut blockchain = BLOCKCHAIN.lock().unwrap();
+    if let Some(storage) = &mut *blockchain {
+        match storage.add_transaction(transaction) {
+            Ok(_) => NBC_STORE_OK,
+            Err(_) => NBC_STORE_ERROR,
+        }
+    } else {
+        NBC_STORE_ERROR
+    }
+}
+
+/// Seal the transactions currently sitting in the mempool into a new block
+/// and commit it, so values stored via `NBC_storeData`/`NBC_storeDataSigned`
+/// become visible to `NBC_retrieveData`/`NBC_getMerkleProof`. Does nothing
+/// (and returns `false`) if the mempool is empty.
+///
+/// Proof-of-Work deployments (`NBC_initBlockchain` called with a nonzero
+/// `pow_difficulty`) mine the block directly; `keypair`/`keypair_len` are
+/// ignored and may be null/0. Proof-of-Authority deployments must first
+/// call `NBC_setValidatorSet` with the sync group's shared validator set,
+/// then pass this device's own ed25519 keypair (64 bytes: secret key
+/// followed by public key) so the block can be signed for its turn.
+/// Returns `false` (without sealing) if it isn't this keypair's turn, or
+/// if no validator set has been configured yet.
+#[no_mangle]
+pub extern "C" fn NBC_sealBlock(keypair: *const u8, keypair_len: size_t) -> bool {
+    let mut blockchain = BLOCKCHAIN.lock().unwrap();
+    let storage = match &mut *blockchain {
+        Some(storage) => storage,
+        None => return false,
+    };
+
+    if storage.mempool().is_empty() {
+        return false;
     }
+
+    let result = match storage.consensus_mode() {
+        ConsensusMode::ProofOfWork { difficulty } => storage.seal_block(|block: &mut Block| {
+            block.mine(difficulty);
+            Ok(())
+        }),
+        ConsensusMode::Authority => {
+            if keypair.is_null() || keypair_len != 64 {
+                return false;
+            }
+            let authority = match configured_authority() {
+                Some(authority) => authority,
+                None => return false,
+            };
+            let keypair_bytes = unsafe { slice::from_raw_parts(keypair, keypair_len) };
+            let keypair = match Keypair::from_bytes(keypair_bytes) {
+                Ok(keypair) => keypair,
+                Err(_) => return false,
+            };
+            storage.seal_block(|block| authority.seal_block(block, &keypair))
+        }
+    };
+
+    result.is_ok()
 }
 
-/// Retrieve data from the blockchain
+/// `NBC_retrieveData` result codes
+pub const NBC_RETRIEVE_ERROR: u32 = 0;
+/// A live value was found and written to `out_data`/`out_len`
+pub const NBC_RETRIEVE_FOUND: u32 = 1;
+/// No value (or no live value) exists for this key
+pub const NBC_RETRIEVE_NOT_FOUND: u32 = 2;
+/// A value exists but its time-lock window excludes the current time;
+/// Swift should trigger a re-auth flow instead of using it
+pub const NBC_RETRIEVE_EXPIRED: u32 = 3;
+
+/// Retrieve data from the blockchain. Returns one of the `NBC_RETRIEVE_*`
+/// result codes; `out_data`/`out_len` are only populated on `NBC_RETRIEVE_FOUND`.
 #[no_mangle]
 pub extern "C" fn NBC_retrieveData(
     key: *const c_char,
     transaction_type: u32,
     out_data: *mut *mut c_void,
     out_len: *mut size_t,
-) -> bool {
+) -> u32 {
     // Check parameters
     if key.is_null() || out_data.is_null() || out_len.is_null() {
-        return false;
+        return NBC_RETRIEVE_ERROR;
     }
-    
+
     // Convert key to Rust string
     let key_cstr = unsafe { CStr::from_ptr(key) };
     let key_str = match key_cstr.to_str() {
         Ok(s) => s.to_string(),
-        Err(_) => return false,
+        Err(_) => return NBC_RETRIEVE_ERROR,
     };
-    
+
     // Determine transaction type
     let tx_type = match transaction_type {
         0 => TransactionType::StoreToken,
@@ -130,43 +402,112 @@ pub extern "C" fn NBC_retrieveData(
         3 => TransactionType::StoreCache,
         4 => TransactionType::UpdateCache,
         5 => TransactionType::DeleteCache,
-        _ => return false,
+        _ => return NBC_RETRIEVE_ERROR,
     };
-    
+
     // Get from blockchain
-    let blockchain = BLOCKCHAIN.lock().unwrap();
-    if let Some(storage) = &*blockchain {
-        match storage.get_latest_for_key(&key_str, &tx_type) {
-            Ok(Some(tx)) => {
-                // Allocate memory for the result
-                let data = tx.data.value;
-                let data_len = data.len();
-                
-                let buffer = unsafe { libc::malloc(data_len) as *mut c_void };
-                if buffer.is_null() {
-                    return false;
-                }
-                
-                // Copy data to output buffer
-                unsafe {
-                    std::ptr::copy_nonoverlapping(
-                        data.as_ptr() as *const c_void,
-                        buffer,
-                        data_len
-                    );
-                    *out_data = buffer;
-                    *out_len = data_len;
-                }
-                
-                true
-            },
-            _ => false,
+    let mut blockchain = BLOCKCHAIN.lock().unwrap();
+    let storage = match &mut *blockchain {
+        Some(storage) => storage,
+        None => return NBC_RETRIEVE_ERROR,
+    };
+
+    match storage.get_latest_for_key(&key_str, &tx_type) {
+        Ok(LookupResult::Found(tx)) => {
+            // Stored values are always encrypted at rest; decrypt before
+            // handing them back to Swift
+            let secret = ENCRYPTION_KEY.lock().unwrap();
+            let data = match secret.as_ref().and_then(|secret| crypto::decrypt(&tx.data.value, secret)) {
+                Some(data) => data,
+                None => return NBC_RETRIEVE_ERROR,
+            };
+            let data_len = data.len();
+
+            let buffer = unsafe { libc::malloc(data_len) as *mut c_void };
+            if buffer.is_null() {
+                return NBC_RETRIEVE_ERROR;
+            }
+
+            // Copy data to output buffer
+            unsafe {
+                std::ptr::copy_nonoverlapping(data.as_ptr() as *const c_void, buffer, data_len);
+                *out_data = buffer;
+                *out_len = data_len;
+            }
+
+            NBC_RETRIEVE_FOUND
         }
-    } else {
-        false
+        Ok(LookupResult::Expired) => NBC_RETRIEVE_EXPIRED,
+        Ok(LookupResult::NotFound) => NBC_RETRIEVE_NOT_FOUND,
+        Err(_) => NBC_RETRIEVE_ERROR,
     }
 }
 
+/// Fetch a Merkle inclusion proof for the latest stored transaction
+/// matching `key` and `transaction_type`, so the proof can be cached and
+/// verified without downloading the full block.
+#[no_mangle]
+pub extern "C" fn NBC_getMerkleProof(
+    key: *const c_char,
+    transaction_type: u32,
+    out_proof: *mut *mut c_void,
+    out_len: *mut size_t,
+) -> bool {
+    // Check parameters
+    if key.is_null() || out_proof.is_null() || out_len.is_null() {
+        return false;
+    }
+
+    // Convert key to Rust string
+    let key_cstr = unsafe { CStr::from_ptr(key) };
+    let key_str = match key_cstr.to_str() {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+
+    // Determine transaction type
+    let tx_type = match transaction_type {
+        0 => TransactionType::StoreToken,
+        1 => TransactionType::UpdateToken,
+        2 => TransactionType::DeleteToken,
+        3 => TransactionType::StoreCache,
+        4 => TransactionType::UpdateCache,
+        5 => TransactionType::DeleteCache,
+        _ => return false,
+    };
+
+    // Fetch proof from the blockchain
+    let mut blockchain = BLOCKCHAIN.lock().unwrap();
+    let storage = match &mut *blockchain {
+        Some(storage) => storage,
+        None => return false,
+    };
+
+    let (root, proof) = match storage.merkle_proof_for_key(key_str, &tx_type) {
+        Ok(Some(result)) => result,
+        _ => return false,
+    };
+
+    let encoded = match bincode::serialize(&(root, proof)) {
+        Ok(encoded) => encoded,
+        Err(_) => return false,
+    };
+
+    let data_len = encoded.len();
+    let buffer = unsafe { libc::malloc(data_len) as *mut c_void };
+    if buffer.is_null() {
+        return false;
+    }
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(encoded.as_ptr() as *const c_void, buffer, data_len);
+        *out_proof = buffer;
+        *out_len = data_len;
+    }
+
+    true
+}
+
 /// Free memory allocated by the FFI layer
 #[no_mangle]
 pub extern "C" fn NBC_freeMemory(ptr: *mut c_void) {
@@ -183,4 +524,73 @@ pub extern "C" fn NBC_shutdownBlockchain() -> bool {
     let mut blockchain = BLOCKCHAIN.lock().unwrap();
     *blockchain = None;
     true
+}
+
+/// Register a peer (`host:port`) to sync with on subsequent `NBC_syncNow`
+/// calls, so the same user's other devices can be reached.
+#[no_mangle]
+pub extern "C" fn NBC_addPeer(addr: *const c_char) -> bool {
+    if addr.is_null() {
+        return false;
+    }
+
+    let addr_cstr = unsafe { CStr::from_ptr(addr) };
+    let addr_str = match addr_cstr.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return false,
+    };
+
+    PEERS.lock().unwrap().add_peer(addr_str);
+    true
+}
+
+/// Start serving this device's chain to peers on `addr` (`host:port`), so
+/// another device's `NBC_addPeer` + `NBC_syncNow` can reach it. Runs on a
+/// background thread for the lifetime of the process; call once, typically
+/// right after `NBC_initBlockchain`. Returns `false` if the blockchain
+/// hasn't been initialized yet or `addr` can't be bound.
+#[no_mangle]
+pub extern "C" fn NBC_startListening(addr: *const c_char) -> bool {
+    if addr.is_null() {
+        return false;
+    }
+
+    let addr_cstr = unsafe { CStr::from_ptr(addr) };
+    let addr_str = match addr_cstr.to_str() {
+        Ok(s) => s.to_string(),
+        Err(_) => return false,
+    };
+
+    if BLOCKCHAIN.lock().unwrap().is_none() {
+        return false;
+    }
+
+    crate::network::spawn_listener(&addr_str, BLOCKCHAIN.clone()).is_ok()
+}
+
+/// Poll every registered peer for blocks the local chain is missing and
+/// commit any that verify, returning the number of blocks committed (or
+/// a negative value if the blockchain hasn't been initialized). This is
+/// one-shot and caller-driven — Swift is expected to call it on its own
+/// periodic schedule (e.g. a repeating timer or background refresh task)
+/// since the library doesn't run a background polling loop of its own.
+/// Proof-of-Work deployments verify each peer's blocks directly;
+/// Proof-of-Authority deployments verify against the validator set
+/// configured via `NBC_setValidatorSet` — every incoming block is
+/// rejected until that's been called.
+#[no_mangle]
+pub extern "C" fn NBC_syncNow() -> i64 {
+    let mut blockchain = BLOCKCHAIN.lock().unwrap();
+    let storage = match &mut *blockchain {
+        Some(storage) => storage,
+        None => return -1,
+    };
+
+    let authority = match storage.consensus_mode() {
+        ConsensusMode::Authority => configured_authority(),
+        ConsensusMode::ProofOfWork { .. } => None,
+    };
+
+    let peers = PEERS.lock().unwrap();
+    peers.sync_now(storage, authority.as_ref()) as i64
 }
\ No newline at end of file
@@ -3,6 +3,8 @@ pub mod consensus;
 pub mod storage;
 pub mod crypto;
 pub mod network;
+#[cfg(feature = "jsonrpc")]
+pub mod rpc;
 
 // Re-export key types for FFI
 pub use block::Block;
@@ -0,0 +1,215 @@
+use crate::block::{self, Block};
+use crate::consensus::{ConsensusMode, ProofOfAuthority};
+use crate::crypto::Hash;
+use crate::storage::BlockchainStorage;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Wire messages exchanged between peers over the length-prefixed bincode protocol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Message {
+    /// Ask a peer for its current chain tip
+    GetHead,
+    /// A peer's current chain tip
+    Head { height: u64, hash: Hash },
+    /// Ask a peer for a run of blocks starting at `from_height`
+    GetBlocks { from_height: u64, count: u64 },
+    /// The requested blocks, in height order
+    Blocks(Vec<Block>),
+}
+
+/// Write a single length-prefixed, bincode-encoded message
+pub fn write_message<W: Write>(writer: &mut W, message: &Message) -> Result<()> {
+    let encoded = bincode::serialize(message)?;
+    writer.write_all(&(encoded.len() as u32).to_be_bytes())?;
+    writer.write_all(&encoded)?;
+    Ok(())
+}
+
+/// Read a single length-prefixed, bincode-encoded message
+pub fn read_message<R: Read>(reader: &mut R) -> Result<Message> {
+    let mut len_bytes = [0u8; 4];
+    reader.read_exact(&mut len_bytes)?;
+    let len = u32::from_be_bytes(len_bytes) as usize;
+
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(bincode::deserialize(&buf)?)
+}
+
+/// Verify an incoming block under whichever consensus mode storage is
+/// configured for. Under Proof-of-Work, `header.difficulty` is peer-claimed
+/// metadata, so it's checked against the locally configured `difficulty`
+/// before trusting `validate_pow` to confirm the block's hash actually
+/// meets it — otherwise a peer could mine at a trivially low difficulty
+/// and still pass.
+fn verify_incoming_block(block: &Block, mode: ConsensusMode, authority: Option<&ProofOfAuthority>) -> Result<()> {
+    match mode {
+        ConsensusMode::Authority => authority
+            .ok_or_else(|| anyhow!("no authority set configured for block verification"))?
+            .verify_block(block),
+        ConsensusMode::ProofOfWork { difficulty } => {
+            if block.header.difficulty < difficulty {
+                return Err(anyhow!(
+                    "block at height {} declares difficulty {}, below the required {}",
+                    block.header.height,
+                    block.header.difficulty,
+                    difficulty
+                ));
+            }
+            if block::validate_pow(block) {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "block at height {} fails its proof-of-work",
+                    block.header.height
+                ))
+            }
+        }
+    }
+}
+
+/// Respond to a single incoming request on an already-accepted connection.
+/// `storage` is `Option`-wrapped to match the FFI layer's global, which
+/// only holds a live `BlockchainStorage` between `NBC_initBlockchain` and
+/// `NBC_shutdownBlockchain`.
+fn handle_request(stream: &mut TcpStream, storage: &Mutex<Option<BlockchainStorage>>) -> Result<()> {
+    let request = read_message(stream)?;
+    let mut storage = storage.lock().unwrap();
+    let storage = storage
+        .as_mut()
+        .ok_or_else(|| anyhow!("blockchain not initialized"))?;
+
+    match request {
+        Message::GetHead => {
+            let height = storage.head_height().unwrap_or(0);
+            let hash = storage
+                .head_hash()
+                .unwrap_or_else(|| blake3::hash(b"genesis").into());
+            write_message(stream, &Message::Head { height, hash })
+        }
+        Message::GetBlocks { from_height, count } => {
+            let mut blocks = Vec::new();
+            for height in from_height..from_height.saturating_add(count) {
+                match storage.get_block(height)? {
+                    Some(block) => blocks.push(block),
+                    None => break,
+                }
+            }
+            write_message(stream, &Message::Blocks(blocks))
+        }
+        other => Err(anyhow!("unexpected request {:?}", other)),
+    }
+}
+
+/// Accept peer connections on `addr` forever, serving `GetHead`/`GetBlocks`
+/// requests against `storage` (so other devices' `NBC_syncNow` can reach
+/// this one — see `NBC_startListening`). Runs on a dedicated background
+/// thread for the lifetime of the process; there is no periodic polling
+/// loop on the serving side, since peers only ever read on demand.
+pub fn spawn_listener(addr: &str, storage: Arc<Mutex<Option<BlockchainStorage>>>) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    thread::spawn(move || {
+        for incoming in listener.incoming() {
+            let Ok(mut stream) = incoming else { continue };
+            let _ = handle_request(&mut stream, &storage);
+        }
+    });
+    Ok(())
+}
+
+/// Holds the configured peer list and drives pulling missing blocks from them
+#[derive(Debug, Clone, Default)]
+pub struct PeerManager {
+    /// Configured peers, as `host:port`
+    peers: Vec<String>,
+}
+
+impl PeerManager {
+    /// Create an empty peer manager
+    pub fn new() -> Self {
+        PeerManager { peers: Vec::new() }
+    }
+
+    /// Add a peer address (`host:port`) to poll during sync
+    pub fn add_peer(&mut self, addr: String) {
+        if !self.peers.contains(&addr) {
+            self.peers.push(addr);
+        }
+    }
+
+    /// Configured peer addresses
+    pub fn peers(&self) -> &[String] {
+        &self.peers
+    }
+
+    /// Poll every configured peer for its head and fast-forward the local
+    /// chain with any blocks it's missing. Each incoming block must verify
+    /// under the storage's consensus mode and must extend the current head;
+    /// forks that don't extend it are rejected for now. Returns the number
+    /// of blocks committed. A single unreachable or misbehaving peer doesn't
+    /// abort syncing with the rest.
+    pub fn sync_now(&self, storage: &mut BlockchainStorage, authority: Option<&ProofOfAuthority>) -> u64 {
+        let mut committed = 0;
+        for addr in &self.peers {
+            match Self::sync_with_peer(addr, storage, authority) {
+                Ok(n) => committed += n,
+                Err(_) => continue,
+            }
+        }
+        committed
+    }
+
+    fn sync_with_peer(addr: &str, storage: &mut BlockchainStorage, authority: Option<&ProofOfAuthority>) -> Result<u64> {
+        let mut stream = TcpStream::connect(addr)?;
+
+        write_message(&mut stream, &Message::GetHead)?;
+        let peer_height = match read_message(&mut stream)? {
+            Message::Head { height, .. } => height,
+            other => return Err(anyhow!("unexpected response to GetHead: {:?}", other)),
+        };
+
+        let from_height = storage.head_height().map(|h| h + 1).unwrap_or(0);
+        if peer_height < from_height {
+            return Ok(0);
+        }
+
+        write_message(
+            &mut stream,
+            &Message::GetBlocks {
+                from_height,
+                count: peer_height - from_height + 1,
+            },
+        )?;
+        let blocks = match read_message(&mut stream)? {
+            Message::Blocks(blocks) => blocks,
+            other => return Err(anyhow!("unexpected response to GetBlocks: {:?}", other)),
+        };
+
+        let mut committed = 0;
+        let mut expected_height = from_height;
+        for block in blocks {
+            verify_incoming_block(&block, storage.consensus_mode(), authority)?;
+
+            let expected_previous = storage
+                .head_hash()
+                .unwrap_or_else(|| blake3::hash(b"genesis").into());
+            // A matching previous_hash alone doesn't rule out a peer
+            // splicing in a block claiming an arbitrary height; require
+            // strict continuity with the local chain too.
+            if block.header.previous_hash != expected_previous || block.header.height != expected_height {
+                break;
+            }
+
+            storage.commit_block(block)?;
+            committed += 1;
+            expected_height += 1;
+        }
+
+        Ok(committed)
+    }
+}
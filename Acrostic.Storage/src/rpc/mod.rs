@@ -0,0 +1,270 @@
+//! Optional JSON-RPC server exposing chain and storage queries over TCP,
+//! for debugging and external integrations that can't link the native FFI.
+//!
+//! Enabled via the `jsonrpc` feature. Each connection speaks
+//! newline-delimited JSON: one [`RpcRequest`] per line in, one
+//! [`RpcResponse`] per line out.
+
+use crate::block::{Transaction, TransactionType};
+use crate::crypto::Hash;
+use crate::storage::{BlockchainStorage, LookupResult};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Shared-secret token required by mutating methods (e.g. `sendtransaction`)
+    #[serde(default)]
+    token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RpcResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl RpcResponse {
+    fn ok(result: Value) -> Self {
+        RpcResponse {
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn err(message: impl Into<String>) -> Self {
+        RpcResponse {
+            result: None,
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// JSON-RPC server over TCP, exposing read-only chain/storage queries plus
+/// `sendtransaction`, which requires `write_token` and is refused outright
+/// when no `write_token` is set.
+pub struct RpcServer {
+    storage: Arc<Mutex<BlockchainStorage>>,
+    /// Shared secret required in `RpcRequest.token` for mutating methods.
+    /// `None` disables write methods entirely (read-only deployment).
+    write_token: Option<String>,
+}
+
+impl RpcServer {
+    pub fn new(storage: Arc<Mutex<BlockchainStorage>>, write_token: Option<String>) -> Self {
+        RpcServer {
+            storage,
+            write_token,
+        }
+    }
+
+    /// Bind `addr` and serve requests forever on a dedicated background thread.
+    pub fn spawn(self, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        thread::spawn(move || {
+            for incoming in listener.incoming() {
+                let Ok(stream) = incoming else { continue };
+                self.handle_connection(stream);
+            }
+        });
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let Ok(write_stream) = stream.try_clone() else {
+            return;
+        };
+        let mut reader = BufReader::new(stream);
+        let mut writer = write_stream;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+
+            let response = match serde_json::from_str::<RpcRequest>(line.trim()) {
+                Ok(request) => self.dispatch(request),
+                Err(e) => RpcResponse::err(format!("invalid request: {}", e)),
+            };
+
+            let Ok(encoded) = serde_json::to_string(&response) else {
+                return;
+            };
+            if writer.write_all(encoded.as_bytes()).is_err() || writer.write_all(b"\n").is_err() {
+                return;
+            }
+        }
+    }
+
+    fn dispatch(&self, request: RpcRequest) -> RpcResponse {
+        match request.method.as_str() {
+            "getblockcount" => self.get_block_count(),
+            "getblock" => self.get_block(&request.params),
+            "getlatest" => self.get_latest(&request.params),
+            "getrawmempool" => self.get_raw_mempool(),
+            "sendtransaction" => self.send_transaction(&request.params, request.token.as_deref()),
+            other => RpcResponse::err(format!("unknown method: {}", other)),
+        }
+    }
+
+    fn get_block_count(&self) -> RpcResponse {
+        let storage = self.storage.lock().unwrap();
+        let count = storage.head_height().map(|h| h + 1).unwrap_or(0);
+        RpcResponse::ok(Value::from(count))
+    }
+
+    fn get_block(&self, params: &Value) -> RpcResponse {
+        let mut storage = self.storage.lock().unwrap();
+        let block = if let Some(height) = params.get("height").and_then(Value::as_u64) {
+            storage.get_block(height)
+        } else if let Some(hex) = params.get("hash").and_then(Value::as_str) {
+            match Hash::from_hex(hex) {
+                Ok(hash) => storage.get_block_by_hash(&hash),
+                Err(e) => return RpcResponse::err(e.to_string()),
+            }
+        } else {
+            return RpcResponse::err("getblock requires a 'height' or 'hash' param");
+        };
+
+        match block {
+            Ok(Some(block)) => to_response(&block),
+            Ok(None) => RpcResponse::err("block not found"),
+            Err(e) => RpcResponse::err(e.to_string()),
+        }
+    }
+
+    fn get_latest(&self, params: &Value) -> RpcResponse {
+        let Some(key) = params.get("key").and_then(Value::as_str) else {
+            return RpcResponse::err("getlatest requires a 'key' param");
+        };
+        let tx_type = match params
+            .get("transaction_type")
+            .and_then(Value::as_u64)
+            .and_then(transaction_type_from_u32)
+        {
+            Some(tx_type) => tx_type,
+            None => return RpcResponse::err("getlatest requires a valid 'transaction_type' param"),
+        };
+
+        let mut storage = self.storage.lock().unwrap();
+        match storage.get_latest_for_key(key, &tx_type) {
+            Ok(LookupResult::Found(transaction)) => to_response(&transaction),
+            Ok(LookupResult::Expired) => RpcResponse::err("value exists but is outside its time-lock window"),
+            Ok(LookupResult::NotFound) => RpcResponse::err("not found"),
+            Err(e) => RpcResponse::err(e.to_string()),
+        }
+    }
+
+    fn get_raw_mempool(&self) -> RpcResponse {
+        let storage = self.storage.lock().unwrap();
+        to_response(storage.mempool())
+    }
+
+    fn send_transaction(&self, params: &Value, token: Option<&str>) -> RpcResponse {
+        let Some(expected_token) = &self.write_token else {
+            return RpcResponse::err("sendtransaction is disabled on this read-only deployment");
+        };
+        if token != Some(expected_token.as_str()) {
+            return RpcResponse::err("invalid or missing token");
+        }
+
+        let Some(encoded) = params.get("transaction").and_then(Value::as_str) else {
+            return RpcResponse::err("sendtransaction requires a 'transaction' param");
+        };
+        let bytes = match decode_hex_or_base64(encoded) {
+            Ok(bytes) => bytes,
+            Err(e) => return RpcResponse::err(e.to_string()),
+        };
+        let transaction: Transaction = match bincode::deserialize(&bytes) {
+            Ok(transaction) => transaction,
+            Err(e) => return RpcResponse::err(format!("malformed transaction: {}", e)),
+        };
+
+        let mut storage = self.storage.lock().unwrap();
+        match storage.add_transaction(transaction) {
+            Ok(()) => RpcResponse::ok(Value::Bool(true)),
+            Err(e) => RpcResponse::err(e.to_string()),
+        }
+    }
+}
+
+fn to_response<T: Serialize>(value: T) -> RpcResponse {
+    match serde_json::to_value(value) {
+        Ok(value) => RpcResponse::ok(value),
+        Err(e) => RpcResponse::err(e.to_string()),
+    }
+}
+
+fn transaction_type_from_u32(n: u64) -> Option<TransactionType> {
+    match n {
+        0 => Some(TransactionType::StoreToken),
+        1 => Some(TransactionType::UpdateToken),
+        2 => Some(TransactionType::DeleteToken),
+        3 => Some(TransactionType::StoreCache),
+        4 => Some(TransactionType::UpdateCache),
+        5 => Some(TransactionType::DeleteCache),
+        _ => None,
+    }
+}
+
+/// Decode a hex or base64 encoded byte string, trying hex first since it's
+/// unambiguous and only falling back to base64 when that fails.
+fn decode_hex_or_base64(encoded: &str) -> Result<Vec<u8>> {
+    if let Ok(bytes) = decode_hex(encoded) {
+        return Ok(bytes);
+    }
+    decode_base64(encoded)
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return Err(anyhow!("odd-length hex string"));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| anyhow!("invalid hex: {}", e)))
+        .collect()
+}
+
+/// Minimal standard-alphabet base64 decoder, mirroring the hand-rolled hex
+/// helpers already used for hashes elsewhere in this crate.
+fn decode_base64(encoded: &str) -> Result<Vec<u8>> {
+    fn value(byte: u8) -> Option<u32> {
+        match byte {
+            b'A'..=b'Z' => Some((byte - b'A') as u32),
+            b'a'..=b'z' => Some((byte - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((byte - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let trimmed = encoded.trim_end_matches('=');
+    let mut out = Vec::with_capacity(trimmed.len() * 3 / 4 + 3);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in trimmed.bytes() {
+        let v = value(byte).ok_or_else(|| anyhow!("not valid hex or base64"))?;
+        buffer = (buffer << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
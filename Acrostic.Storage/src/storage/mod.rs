@@ -1,101 +1,422 @@
 use crate::block::{Block, Transaction, TransactionType};
+use crate::consensus::ConsensusMode;
+use crate::crypto::Hash;
 use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use std::num::NonZeroUsize;
 use std::path::Path;
 use leveldb::database::Database;
 use leveldb::options::{Options, ReadOptions, WriteOptions};
 use leveldb::kv::KV;
+use lru::LruCache;
 use bincode;
+use serde::{Deserialize, Serialize};
+
+/// Pointer to the current chain tip
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct HeadPointer {
+    hash: Hash,
+    height: u64,
+}
+
+/// Current-state index entry for a single key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateEntry {
+    /// Transaction that most recently committed this key (kept even when
+    /// `tombstoned`, so the deletion itself remains auditable)
+    transaction: Transaction,
+    /// Height of the block that committed this entry
+    committed_height: u64,
+    /// Timestamp of the block that committed this entry, used to resolve
+    /// the transaction's `relative_locktime_millis`
+    committed_at: DateTime<Utc>,
+    /// Whether this entry represents a deletion
+    tombstoned: bool,
+}
+
+/// Outcome of a point lookup against the state index
+#[derive(Debug, Clone)]
+pub enum LookupResult {
+    /// No live entry exists for this key (never stored, or deleted)
+    NotFound,
+    /// An entry exists but its time-lock window excludes the current time
+    Expired,
+    /// A live, in-window transaction
+    Found(Transaction),
+}
+
+/// Read cache hit/miss counters for [`BlockchainStorage`]'s LRU layer
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheStats {
+    pub block_hits: u64,
+    pub block_misses: u64,
+    pub state_hits: u64,
+    pub state_misses: u64,
+}
 
 /// Storage interface for the blockchain
 pub struct BlockchainStorage {
-    /// LevelDB database for blocks
+    /// LevelDB database holding the immutable block log, keyed by both
+    /// height (`block:{height}`) and hash (`block:{hex hash}`)
     blocks_db: Database<Vec<u8>>,
-    /// LevelDB database for transactions
-    transactions_db: Database<Vec<u8>>,
-    /// Current head block hash
-    head_hash: Option<Vec<u8>>,
+    /// LevelDB database holding only the latest state per key
+    /// (`state:{category}:{key}`), for O(1) reads
+    state_db: Database<Vec<u8>>,
+    /// Transactions waiting to be included in the next sealed block
+    mempool: Vec<Transaction>,
+    /// Current chain tip, if any blocks have been sealed yet
+    head: Option<HeadPointer>,
+    /// Consensus mode used to seal new blocks
+    consensus_mode: ConsensusMode,
+    /// Read-through cache for committed blocks, keyed by their storage key
+    blocks_cache: LruCache<Vec<u8>, Block>,
+    /// Read-through cache for the latest transaction per state key, paired
+    /// with the timestamp of the block that committed it
+    state_cache: LruCache<String, (Transaction, DateTime<Utc>)>,
+    cache_stats: CacheStats,
 }
 
 impl BlockchainStorage {
-    /// Create a new blockchain storage at the given path
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+    /// Create a new blockchain storage at the given path, sealing new
+    /// blocks under `consensus_mode` (Proof-of-Authority or Proof-of-Work).
+    /// `block_cache_capacity` and `state_cache_capacity` size the LRU read
+    /// caches in front of LevelDB.
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        consensus_mode: ConsensusMode,
+        block_cache_capacity: usize,
+        state_cache_capacity: usize,
+    ) -> Result<Self> {
         let path = path.as_ref();
-        
+
         // Create directory if it doesn't exist
         std::fs::create_dir_all(path)?;
-        
-        // Open or create blocks database
+
+        // Open or create the block log database
         let blocks_path = path.join("blocks");
         let mut options = Options::new();
         options.create_if_missing = true;
         let blocks_db = Database::open(&blocks_path, options.clone())?;
-        
-        // Open or create transactions database
-        let tx_path = path.join("transactions");
-        let transactions_db = Database::open(&tx_path, options)?;
-        
-        // Get head hash
-        let mut read_opts = ReadOptions::new();
-        let head_hash = blocks_db.get(read_opts, b"HEAD".to_vec())
-            .map_err(|e| anyhow!("Failed to read HEAD: {}", e))?;
-        
+
+        // Open or create the state index database
+        let state_path = path.join("state");
+        let state_db = Database::open(&state_path, options)?;
+
+        // Recover the chain tip, if one was ever sealed
+        let read_opts = ReadOptions::new();
+        let head = blocks_db
+            .get(read_opts, b"HEAD".to_vec())
+            .map_err(|e| anyhow!("Failed to read HEAD: {}", e))?
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
+
         Ok(BlockchainStorage {
             blocks_db,
-            transactions_db,
-            head_hash,
+            state_db,
+            mempool: Vec::new(),
+            head,
+            consensus_mode,
+            blocks_cache: LruCache::new(NonZeroUsize::new(block_cache_capacity).unwrap_or(NonZeroUsize::MIN)),
+            state_cache: LruCache::new(NonZeroUsize::new(state_cache_capacity).unwrap_or(NonZeroUsize::MIN)),
+            cache_stats: CacheStats::default(),
         })
     }
-    
-    /// Add a transaction to the blockchain
-    pub fn add_transaction(&self, transaction: Transaction) -> Result<()> {
-        // In a real implementation, this would:
-        // 1. Validate the transaction
-        // 2. Add it to a pending transaction pool
-        // 3. Eventually include it in a block
-        // 4. Commit the block to the chain
-        
-        // For simplicity, we'll just store the transaction directly
-        let key = format!("tx:{}:{}", transaction.data.key, chrono::Utc::now().timestamp_millis());
-        let data = bincode::serialize(&transaction)?;
-        
-        let write_opts = WriteOptions::new();
-        self.transactions_db.put(write_opts, key.as_bytes().to_vec(), data)
-            .map_err(|e| anyhow!("Failed to store transaction: {}", e))?;
-        
+
+    /// The consensus mode this storage seals new blocks under
+    pub fn consensus_mode(&self) -> ConsensusMode {
+        self.consensus_mode
+    }
+
+    /// Height of the current chain tip, if any block has been committed yet
+    pub fn head_height(&self) -> Option<u64> {
+        self.head.map(|head| head.height)
+    }
+
+    /// Hash of the current chain tip, if any block has been committed yet
+    pub fn head_hash(&self) -> Option<Hash> {
+        self.head.map(|head| head.hash)
+    }
+
+    /// Read cache hit/miss counters, for tuning cache capacities
+    pub fn stats(&self) -> CacheStats {
+        self.cache_stats
+    }
+
+    /// Queue a transaction in the mempool to be included in the next sealed block
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<()> {
+        self.mempool.push(transaction);
+        Ok(())
+    }
+
+    /// Transactions currently waiting to be sealed into a block
+    pub fn mempool(&self) -> &[Transaction] {
+        &self.mempool
+    }
+
+    /// Drain the mempool into a new block linked to the current head, hand
+    /// it to `seal` to apply consensus-specific sealing (PoA signing or PoW
+    /// mining), then commit it. If `seal` fails, the drained transactions
+    /// are put back into the mempool.
+    pub fn seal_block<F>(&mut self, seal: F) -> Result<Block>
+    where
+        F: FnOnce(&mut Block) -> Result<()>,
+    {
+        let transactions = std::mem::take(&mut self.mempool);
+        let previous_hash = self
+            .head
+            .map(|head| head.hash)
+            .unwrap_or_else(|| blake3::hash(b"genesis").into());
+        let height = self.head.map(|head| head.height + 1).unwrap_or(0);
+
+        let mut block = Block::new(previous_hash, height, transactions);
+        if let Err(e) = seal(&mut block) {
+            self.mempool = block.transactions;
+            return Err(e);
+        }
+        self.commit_block(block.clone())?;
+        Ok(block)
+    }
+
+    /// Append an already-sealed block to the log, advance the head, and
+    /// apply its transactions to the state index. Used both for locally
+    /// sealed blocks and for verified blocks received from peers.
+    pub fn commit_block(&mut self, block: Block) -> Result<()> {
+        let hash = block.hash();
+        let height = block.header.height;
+        let block_bytes = bincode::serialize(&block)?;
+
+        self.blocks_db
+            .put(
+                WriteOptions::new(),
+                format!("block:{}", height).as_bytes().to_vec(),
+                block_bytes.clone(),
+            )
+            .map_err(|e| anyhow!("Failed to store block {}: {}", height, e))?;
+        self.blocks_db
+            .put(
+                WriteOptions::new(),
+                format!("block:{}", hash.to_hex()).as_bytes().to_vec(),
+                block_bytes,
+            )
+            .map_err(|e| anyhow!("Failed to index block {}: {}", hash.to_hex(), e))?;
+
+        let head = HeadPointer { hash, height };
+        self.blocks_db
+            .put(WriteOptions::new(), b"HEAD".to_vec(), bincode::serialize(&head)?)
+            .map_err(|e| anyhow!("Failed to update HEAD: {}", e))?;
+
+        self.blocks_cache
+            .put(format!("block:{}", height).into_bytes(), block.clone());
+        self.blocks_cache
+            .put(format!("block:{}", hash.to_hex()).into_bytes(), block.clone());
+
+        self.apply_transactions_to_state(&block)?;
+        self.head = Some(head);
+
         Ok(())
     }
-    
-    /// Get the latest transaction for a key and type
-    pub fn get_latest_for_key(&self, key: &str, tx_type: &TransactionType) -> Result<Option<Transaction>> {
-        // This is a simplified implementation
-        // In reality, you'd query the state database or scan blocks
-        
-        // Prefix for this key
-        let prefix = format!("tx:{}", key);
-        
-        let mut read_opts = ReadOptions::new();
-        // Start iterating from the prefix
-        read_opts.set_iterate_upper_bound(format!("tx:{}:", key).as_bytes().to_vec());
-        
-        let mut iter = self.transactions_db.iter(read_opts);
-        iter.seek(&prefix.as_bytes().to_vec());
-        
-        let mut latest: Option<Transaction> = None;
-        let mut latest_time = 0i64;
-        
-        while let Some((_, value)) = iter.next() {
-            if let Ok(tx) = bincode::deserialize::<Transaction>(&value) {
-                // Check if this is the type we're looking for
-                if &tx.transaction_type == tx_type {
-                    let time = tx.timestamp.timestamp_millis();
-                    if time > latest_time {
-                        latest_time = time;
-                        latest = Some(tx);
-                    }
-                }
+
+    /// Apply a block's transactions to the state index, overwriting each
+    /// key's latest entry or tombstoning it for delete-type transactions
+    fn apply_transactions_to_state(&mut self, block: &Block) -> Result<()> {
+        for transaction in &block.transactions {
+            let state_key = format!(
+                "state:{}:{}",
+                transaction.transaction_type.state_category(),
+                transaction.data.key
+            );
+            let entry = StateEntry {
+                tombstoned: transaction.transaction_type.is_delete(),
+                committed_height: block.header.height,
+                committed_at: block.header.timestamp,
+                transaction: transaction.clone(),
+            };
+            self.state_db
+                .put(
+                    WriteOptions::new(),
+                    state_key.as_bytes().to_vec(),
+                    bincode::serialize(&entry)?,
+                )
+                .map_err(|e| anyhow!("Failed to update state for {}: {}", state_key, e))?;
+
+            if entry.tombstoned {
+                self.state_cache.pop(&state_key);
+            } else {
+                self.state_cache.put(state_key, (entry.transaction, entry.committed_at));
             }
         }
-        
-        Ok(latest)
+        Ok(())
+    }
+
+    /// Get the latest committed transaction for a key and type, a single
+    /// point lookup in the state index (read-through the LRU state cache).
+    /// Transactions outside their time-lock window report as `Expired`
+    /// rather than silently vanishing, so callers can trigger re-auth.
+    pub fn get_latest_for_key(&mut self, key: &str, tx_type: &TransactionType) -> Result<LookupResult> {
+        let state_key = format!("state:{}:{}", tx_type.state_category(), key);
+
+        if let Some((transaction, committed_at)) = self.state_cache.get(&state_key) {
+            self.cache_stats.state_hits += 1;
+            return Ok(Self::resolve_lookup(transaction.clone(), *committed_at));
+        }
+        self.cache_stats.state_misses += 1;
+
+        let read_opts = ReadOptions::new();
+        let bytes = self
+            .state_db
+            .get(read_opts, state_key.as_bytes().to_vec())
+            .map_err(|e| anyhow!("Failed to read state for {}: {}", state_key, e))?;
+
+        let entry: StateEntry = match bytes {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None => return Ok(LookupResult::NotFound),
+        };
+
+        if entry.tombstoned {
+            return Ok(LookupResult::NotFound);
+        }
+
+        self.state_cache
+            .put(state_key, (entry.transaction.clone(), entry.committed_at));
+        Ok(Self::resolve_lookup(entry.transaction, entry.committed_at))
+    }
+
+    /// Check a state entry's time-locks against the current time
+    fn resolve_lookup(transaction: Transaction, committed_at: DateTime<Utc>) -> LookupResult {
+        if transaction.is_valid_at(Utc::now(), committed_at) {
+            LookupResult::Found(transaction)
+        } else {
+            LookupResult::Expired
+        }
+    }
+
+    /// Fetch a committed block by height (read-through the LRU block cache)
+    pub fn get_block(&mut self, height: u64) -> Result<Option<Block>> {
+        self.get_block_by_storage_key(format!("block:{}", height))
+    }
+
+    /// Fetch a committed block by hash (read-through the LRU block cache)
+    pub fn get_block_by_hash(&mut self, hash: &Hash) -> Result<Option<Block>> {
+        self.get_block_by_storage_key(format!("block:{}", hash.to_hex()))
+    }
+
+    fn get_block_by_storage_key(&mut self, storage_key: String) -> Result<Option<Block>> {
+        let cache_key = storage_key.into_bytes();
+
+        if let Some(block) = self.blocks_cache.get(&cache_key) {
+            self.cache_stats.block_hits += 1;
+            return Ok(Some(block.clone()));
+        }
+        self.cache_stats.block_misses += 1;
+
+        let read_opts = ReadOptions::new();
+        let bytes = self
+            .blocks_db
+            .get(read_opts, cache_key.clone())
+            .map_err(|e| anyhow!("Failed to read block: {}", e))?;
+        let block: Option<Block> = bytes.map(|b| bincode::deserialize(&b)).transpose()?;
+
+        if let Some(block) = &block {
+            self.blocks_cache.put(cache_key, block.clone());
+        }
+        Ok(block)
     }
-}
\ No newline at end of file
+
+    /// Build a Merkle inclusion proof for the latest committed transaction
+    /// stored under `key`, proving its membership in the block that
+    /// actually committed it.
+    pub fn merkle_proof_for_key(
+        &mut self,
+        key: &str,
+        tx_type: &TransactionType,
+    ) -> Result<Option<(Hash, Vec<(Hash, bool)>)>> {
+        let state_key = format!("state:{}:{}", tx_type.state_category(), key);
+        let read_opts = ReadOptions::new();
+        let bytes = self
+            .state_db
+            .get(read_opts, state_key.as_bytes().to_vec())
+            .map_err(|e| anyhow!("Failed to read state for {}: {}", state_key, e))?;
+
+        let entry: StateEntry = match bytes {
+            Some(bytes) => bincode::deserialize(&bytes)?,
+            None => return Ok(None),
+        };
+        if entry.tombstoned {
+            return Ok(None);
+        }
+
+        let block = self
+            .get_block(entry.committed_height)?
+            .ok_or_else(|| anyhow!("state points at missing block {}", entry.committed_height))?;
+        let tx_index = block
+            .transactions
+            .iter()
+            .position(|tx| tx.data.key == entry.transaction.data.key && tx.timestamp == entry.transaction.timestamp)
+            .ok_or_else(|| anyhow!("committing transaction not found in block {}", entry.committed_height))?;
+        let proof = block
+            .merkle_proof(tx_index)
+            .ok_or_else(|| anyhow!("failed to build merkle proof"))?;
+
+        Ok(Some((block.header.merkle_root, proof)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{verify_merkle_proof, Transaction, TransactionData, TransactionType};
+    use crate::consensus::ConsensusMode;
+    use std::collections::HashMap;
+
+    fn temp_storage(name: &str) -> BlockchainStorage {
+        let path = std::env::temp_dir().join(format!("acrostic-storage-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        BlockchainStorage::new(path, ConsensusMode::ProofOfWork { difficulty: 0 }, 8, 8).unwrap()
+    }
+
+    fn store_tx(key: &str) -> Transaction {
+        Transaction {
+            transaction_type: TransactionType::StoreToken,
+            data: TransactionData {
+                key: key.to_string(),
+                value: b"ciphertext".to_vec(),
+                metadata: HashMap::new(),
+                not_before: None,
+                not_after: None,
+                relative_locktime_millis: None,
+            },
+            timestamp: Utc::now(),
+            signature: Vec::new(),
+            public_key: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn seal_then_fetch_merkle_proof_verifies() {
+        let mut storage = temp_storage("merkle");
+
+        storage.add_transaction(store_tx("token-1")).unwrap();
+        storage
+            .seal_block(|block| {
+                block.mine(0);
+                Ok(())
+            })
+            .unwrap();
+
+        let (root, proof) = storage
+            .merkle_proof_for_key("token-1", &TransactionType::StoreToken)
+            .unwrap()
+            .expect("key committed in the sealed block should have a merkle proof");
+
+        let tx = match storage
+            .get_latest_for_key("token-1", &TransactionType::StoreToken)
+            .unwrap()
+        {
+            LookupResult::Found(tx) => tx,
+            other => panic!("expected Found, got {:?}", other),
+        };
+        let leaf = blake3::hash(&bincode::serialize(&tx).unwrap()).into();
+
+        assert!(verify_merkle_proof(leaf, &proof, &root));
+    }
+}